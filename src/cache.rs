@@ -0,0 +1,135 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use tokio::sync::RwLock;
+
+use crate::error::StoreError;
+use crate::models::{Message, ResolvedMessage, User};
+use crate::store::{MessageStore, Page};
+
+const TTL: Duration = Duration::from_secs(30 * 60);
+
+struct Entry {
+    user: User,
+    expires_at: Instant,
+}
+
+/// Concurrent `name -> User` cache with a fixed TTL, used to skip the
+/// `SELECT ... WHERE name = ?` round-trip that every `get_or_create_user`
+/// call would otherwise make.
+#[derive(Clone, Default)]
+pub struct UserCache {
+    entries: Arc<RwLock<HashMap<String, Entry>>>,
+}
+
+impl UserCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    async fn get(&self, name: &str) -> Option<User> {
+        let entries = self.entries.read().await;
+        entries.get(name).and_then(|entry| {
+            (entry.expires_at > Instant::now()).then(|| entry.user.clone())
+        })
+    }
+
+    async fn insert(&self, user: User) {
+        self.entries.write().await.insert(
+            user.name.clone(),
+            Entry {
+                user,
+                expires_at: Instant::now() + TTL,
+            },
+        );
+    }
+
+    /// Drops every entry past its TTL. Meant to run on a timer so the map
+    /// doesn't grow unbounded with names that are no longer active.
+    pub async fn evict_expired(&self) {
+        let now = Instant::now();
+        self.entries
+            .write()
+            .await
+            .retain(|_, entry| entry.expires_at > now);
+    }
+}
+
+/// `MessageStore` decorator that serves `get_or_create_user` out of a
+/// [`UserCache`] before falling back to `inner`, so the hot send/read path
+/// avoids repeated user lookups.
+pub struct CachingStore<S> {
+    inner: S,
+    cache: UserCache,
+}
+
+impl<S: MessageStore> CachingStore<S> {
+    pub fn new(inner: S) -> Self {
+        CachingStore {
+            inner,
+            cache: UserCache::new(),
+        }
+    }
+
+    /// A handle to the underlying cache, for spawning the background
+    /// eviction task.
+    pub fn cache(&self) -> UserCache {
+        self.cache.clone()
+    }
+}
+
+#[async_trait]
+impl<S: MessageStore> MessageStore for CachingStore<S> {
+    async fn get_or_create_user(&self, name: &str) -> Result<User, StoreError> {
+        if let Some(user) = self.cache.get(name).await {
+            return Ok(user);
+        }
+
+        let user = self.inner.get_or_create_user(name).await?;
+        self.cache.insert(user.clone()).await;
+        Ok(user)
+    }
+
+    async fn update_profile(
+        &self,
+        name: &str,
+        timezone: &str,
+        locale: &str,
+    ) -> Result<User, StoreError> {
+        let user = self.inner.update_profile(name, timezone, locale).await?;
+        self.cache.insert(user.clone()).await;
+        Ok(user)
+    }
+
+    async fn insert_message(&self, message: ResolvedMessage) -> Result<Message, StoreError> {
+        self.inner.insert_message(message).await
+    }
+
+    async fn insert_messages(
+        &self,
+        messages: Vec<ResolvedMessage>,
+    ) -> Result<Vec<Message>, StoreError> {
+        self.inner.insert_messages(messages).await
+    }
+
+    async fn inbox(
+        &self,
+        target_id: i32,
+        limit: Option<i64>,
+        before: Option<i32>,
+    ) -> Result<Page, StoreError> {
+        self.inner.inbox(target_id, limit, before).await
+    }
+
+    async fn conversation(
+        &self,
+        me_id: i32,
+        other_id: i32,
+        limit: Option<i64>,
+        before: Option<i32>,
+    ) -> Result<Page, StoreError> {
+        self.inner.conversation(me_id, other_id, limit, before).await
+    }
+}
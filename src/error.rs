@@ -0,0 +1,32 @@
+use axum::{http::StatusCode, response::IntoResponse, response::Response};
+
+/// Error surfaced by a [`crate::store::MessageStore`] implementation.
+///
+/// Handlers convert these into HTTP responses via [`IntoResponse`] instead of
+/// building `(StatusCode, String)` tuples inline at every call site.
+#[derive(Debug, thiserror::Error)]
+pub enum StoreError {
+    #[error("database error: {0}")]
+    Database(#[from] sqlx::Error),
+    #[error("message {index} in batch failed: {source}")]
+    BatchFailed {
+        index: usize,
+        source: Box<StoreError>,
+    },
+}
+
+impl StoreError {
+    fn status(&self) -> StatusCode {
+        match self {
+            StoreError::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            StoreError::BatchFailed { source, .. } => source.status(),
+        }
+    }
+}
+
+impl IntoResponse for StoreError {
+    fn into_response(self) -> Response {
+        let status = self.status();
+        (status, self.to_string()).into_response()
+    }
+}
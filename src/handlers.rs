@@ -0,0 +1,165 @@
+use std::collections::HashMap;
+
+use axum::{Extension, Json};
+use chrono::Utc;
+
+use crate::error::StoreError;
+use crate::models::{
+    BatchResponse, IncomingMessage, InboxRequest, Message, MessagePage, MessagesRequest,
+    ProfileUpdateRequest, ResolvedMessage, User,
+};
+use crate::state::AppState;
+
+pub async fn get_inbox(
+    Extension(state): Extension<AppState>,
+    Json(payload): Json<InboxRequest>,
+) -> Result<Json<MessagePage>, StoreError> {
+    tracing::info!("Got request for inbox for {}", payload.target);
+
+    let user = state.store.get_or_create_user(&payload.target).await?;
+    let page = state
+        .store
+        .inbox(user.id, payload.limit, payload.before)
+        .await?;
+
+    Ok(Json(MessagePage {
+        messages: page
+            .messages
+            .into_iter()
+            .map(|m| m.for_viewer(&user))
+            .collect(),
+        next_cursor: page.next_cursor,
+    }))
+}
+
+pub async fn get_messages(
+    Extension(state): Extension<AppState>,
+    Json(payload): Json<MessagesRequest>,
+) -> Result<Json<MessagePage>, StoreError> {
+    tracing::info!("Got request for messages for {:?}", payload);
+
+    let me = state.store.get_or_create_user(&payload.me).await?;
+    let other = state.store.get_or_create_user(&payload.other).await?;
+
+    let page = state
+        .store
+        .conversation(me.id, other.id, payload.limit, payload.before)
+        .await?;
+
+    Ok(Json(MessagePage {
+        messages: page
+            .messages
+            .into_iter()
+            .map(|m| m.for_viewer(&me))
+            .filter(|message| me.id == other.id || message.author != message.target)
+            .collect(),
+        next_cursor: page.next_cursor,
+    }))
+}
+
+pub async fn send_message(
+    Extension(state): Extension<AppState>,
+    Json(payload): Json<IncomingMessage>,
+) -> Result<axum::http::StatusCode, StoreError> {
+    tracing::info!("Sending message {:?}", payload);
+
+    let author = state.store.get_or_create_user(&payload.author).await?;
+    let target = state.store.get_or_create_user(&payload.target).await?;
+    let message = ResolvedMessage {
+        author,
+        target,
+        text: payload.text,
+        timestamp: Utc::now(),
+    };
+    let stored = state.store.insert_message(message).await?;
+    push_over_websocket(&state, &stored).await;
+
+    Ok(axum::http::StatusCode::OK)
+}
+
+pub async fn send_batch(
+    Extension(state): Extension<AppState>,
+    Json(payload): Json<Vec<IncomingMessage>>,
+) -> Result<Json<BatchResponse>, StoreError> {
+    tracing::info!("Sending batch of {} messages", payload.len());
+
+    // Resolve each distinct author/target name once (through the cache),
+    // rather than once per message.
+    let mut resolved_users: HashMap<String, User> = HashMap::new();
+    let mut messages = Vec::with_capacity(payload.len());
+    for incoming in payload {
+        let author = resolve_user(&state, &mut resolved_users, incoming.author).await?;
+        let target = resolve_user(&state, &mut resolved_users, incoming.target).await?;
+        messages.push(ResolvedMessage {
+            author,
+            target,
+            text: incoming.text,
+            timestamp: Utc::now(),
+        });
+    }
+
+    let stored = state.store.insert_messages(messages).await?;
+
+    for message in &stored {
+        push_over_websocket(&state, message).await;
+    }
+
+    Ok(Json(BatchResponse {
+        ids: stored.into_iter().map(|m| m.id).collect(),
+    }))
+}
+
+async fn resolve_user(
+    state: &AppState,
+    resolved: &mut HashMap<String, User>,
+    name: String,
+) -> Result<User, StoreError> {
+    if let Some(user) = resolved.get(&name) {
+        return Ok(user.clone());
+    }
+    let user = state.store.get_or_create_user(&name).await?;
+    resolved.insert(name, user.clone());
+    Ok(user)
+}
+
+/// Best-effort push of an already-stored message to a live websocket. Since
+/// the message is already durably committed, nothing here is surfaced as a
+/// request error: with no connected socket it returns silently, and a lookup
+/// error resolving the target's profile or a closed channel is logged and
+/// swallowed.
+async fn push_over_websocket(state: &AppState, message: &Message) {
+    let sender = state.connections.read().await.get(&message.target).cloned();
+    let Some(sender) = sender else {
+        return;
+    };
+
+    let target = match state.store.get_or_create_user(&message.target).await {
+        Ok(target) => target,
+        Err(e) => {
+            tracing::warn!(
+                "Failed to resolve {} for websocket push: {}",
+                message.target,
+                e
+            );
+            return;
+        }
+    };
+
+    if sender.send(message.clone().for_viewer(&target)).await.is_err() {
+        tracing::warn!("Websocket channel for {} closed before send", message.target);
+    }
+}
+
+pub async fn update_profile(
+    Extension(state): Extension<AppState>,
+    Json(payload): Json<ProfileUpdateRequest>,
+) -> Result<Json<User>, StoreError> {
+    tracing::info!("Updating profile for {:?}", payload);
+
+    let user = state
+        .store
+        .update_profile(&payload.name, &payload.timezone, &payload.locale)
+        .await?;
+
+    Ok(Json(user))
+}
@@ -0,0 +1,120 @@
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct IncomingMessage {
+    pub author: String,
+    pub target: String,
+    pub text: String,
+}
+
+#[derive(Serialize, Clone, Debug, sqlx::FromRow)]
+pub struct Message {
+    pub id: i32,
+    pub author: String,
+    pub target: String,
+    pub text: String,
+    pub timestamp: DateTime<Utc>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct OutgoingMessage {
+    pub id: i32,
+    pub author: String,
+    pub target: String,
+    pub text: String,
+    pub timestamp: String,
+}
+
+/// Default timezone/locale for newly created users. Note this is an IANA
+/// zone (with DST), not the old hardcoded `UTC-5` offset, so default-profile
+/// users will see timestamps shift by an hour during standard time.
+pub const DEFAULT_TIMEZONE: &str = "America/Chicago";
+pub const DEFAULT_LOCALE: &str = "en-US";
+
+impl Message {
+    /// Renders this message in `viewer`'s timezone and locale. The same
+    /// stored message is rendered differently depending on who's looking at
+    /// it, so this is a method taking the viewer rather than a plain `From`
+    /// conversion.
+    pub fn for_viewer(self, viewer: &User) -> OutgoingMessage {
+        let tz: Tz = viewer.timezone.parse().unwrap_or(chrono_tz::America::Chicago);
+        let local_time = self.timestamp.with_timezone(&tz);
+        OutgoingMessage {
+            id: self.id,
+            author: self.author,
+            text: self.text,
+            target: self.target,
+            timestamp: format!("{}", local_time.format(locale_format(&viewer.locale))),
+        }
+    }
+}
+
+fn locale_format(locale: &str) -> &'static str {
+    match locale {
+        "en-US" => "%_m/%_d/%Y %_I:%M%p",
+        _ => "%Y-%m-%d %H:%M",
+    }
+}
+
+#[derive(Serialize, Clone, Debug, sqlx::FromRow)]
+pub struct User {
+    pub id: i32,
+    pub name: String,
+    pub timezone: String,
+    pub locale: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ProfileUpdateRequest {
+    pub name: String,
+    pub timezone: String,
+    pub locale: String,
+}
+
+/// A message with its author/target already resolved to `User`s, ready to
+/// insert. Resolving ahead of time (through the cache, see
+/// `crate::cache::CachingStore`) keeps the insert path itself from having to
+/// do its own `SELECT ... WHERE name = ?` lookups.
+pub struct ResolvedMessage {
+    pub author: User,
+    pub target: User,
+    pub text: String,
+    pub timestamp: DateTime<Utc>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct InboxRequest {
+    pub target: String,
+    /// Max messages to return, newest first before reversing to ascending order.
+    pub limit: Option<i64>,
+    /// Only return messages older than this message id (for paging backwards).
+    /// Applied only together with `limit`; ignored when `limit` is absent.
+    pub before: Option<i32>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct MessagesRequest {
+    pub me: String,
+    pub other: String,
+    pub limit: Option<i64>,
+    pub before: Option<i32>,
+}
+
+/// A page of messages plus the cursor to pass as `before` to fetch the next
+/// (older) page. `next_cursor` is `None` when the page came back with fewer
+/// than `limit` messages or no `limit` was given; a full page always carries
+/// a cursor, even if nothing older remains.
+#[derive(Serialize, Debug)]
+pub struct MessagePage {
+    pub messages: Vec<OutgoingMessage>,
+    pub next_cursor: Option<i32>,
+}
+
+/// Ids assigned to a successfully inserted `/send_batch` request, in the
+/// same order as the submitted messages.
+#[derive(Serialize, Debug)]
+pub struct BatchResponse {
+    pub ids: Vec<i32>,
+}
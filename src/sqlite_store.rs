@@ -0,0 +1,269 @@
+use async_trait::async_trait;
+use sqlx::{query, SqliteConnection, SqlitePool};
+
+use crate::error::StoreError;
+use crate::models::{Message, ResolvedMessage, User, DEFAULT_LOCALE, DEFAULT_TIMEZONE};
+use crate::store::{MessageStore, Page};
+
+/// `MessageStore` backed by a `sqlx::SqlitePool`.
+pub struct SqliteStore {
+    pool: SqlitePool,
+}
+
+impl SqliteStore {
+    /// Connects to `path` and ensures the `users`/`messages` tables exist.
+    pub async fn connect(path: &str) -> Result<Self, sqlx::Error> {
+        let pool = SqlitePool::connect(path).await?;
+
+        query(&format!(
+            "CREATE TABLE IF NOT EXISTS users (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL,
+            timezone TEXT NOT NULL DEFAULT '{DEFAULT_TIMEZONE}',
+            locale TEXT NOT NULL DEFAULT '{DEFAULT_LOCALE}'
+        )"
+        ))
+        .execute(&pool)
+        .await?;
+
+        // Migrate pre-existing databases created before timezone/locale existed;
+        // ignore the error when the columns are already present.
+        let _ = query(&format!(
+            "ALTER TABLE users ADD COLUMN timezone TEXT NOT NULL DEFAULT '{DEFAULT_TIMEZONE}'"
+        ))
+        .execute(&pool)
+        .await;
+        let _ = query(&format!(
+            "ALTER TABLE users ADD COLUMN locale TEXT NOT NULL DEFAULT '{DEFAULT_LOCALE}'"
+        ))
+        .execute(&pool)
+        .await;
+
+        query(
+            "CREATE TABLE IF NOT EXISTS messages (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            author TEXT NOT NULL,
+            target TEXT NOT NULL,
+            text TEXT NOT NULL,
+            timestamp DATETIME NOT NULL
+        )",
+        )
+        .execute(&pool)
+        .await?;
+
+        Ok(SqliteStore { pool })
+    }
+}
+
+async fn create_user(
+    connection: &mut SqliteConnection,
+    name: &str,
+) -> Result<sqlx::sqlite::SqliteQueryResult, sqlx::Error> {
+    query("INSERT INTO users (name) VALUES (?)")
+        .bind(name)
+        .execute(connection)
+        .await
+}
+
+async fn get_user(connection: &mut SqliteConnection, name: &str) -> Result<User, sqlx::Error> {
+    sqlx::query_as("SELECT * FROM users WHERE name = ?")
+        .bind(name)
+        .fetch_one(connection)
+        .await
+}
+
+/// Inserts a message whose author/target are already resolved `User`s, so
+/// this does exactly one query (no per-message user lookups).
+async fn insert_one(
+    conn: &mut SqliteConnection,
+    message: ResolvedMessage,
+) -> Result<Message, StoreError> {
+    let result =
+        sqlx::query("INSERT INTO messages (author, target, text, timestamp) VALUES (?, ?, ?, ?)")
+            .bind(message.author.id)
+            .bind(message.target.id)
+            .bind(&message.text)
+            .bind(message.timestamp)
+            .execute(conn)
+            .await?;
+
+    tracing::info!(
+        "Inserted message with id {} and result {:?}",
+        result.last_insert_rowid(),
+        result
+    );
+
+    Ok(Message {
+        id: result.last_insert_rowid() as i32,
+        author: message.author.name,
+        target: message.target.name,
+        text: message.text,
+        timestamp: message.timestamp,
+    })
+}
+
+const MESSAGE_SELECT: &str = "SELECT messages.text, messages.timestamp, author_name AS author, target_name as target, messages.id FROM messages INNER JOIN (SELECT name AS author_name, id AS author_id FROM users) ON messages.author = author_id INNER JOIN (SELECT name AS target_name, id AS target_id FROM users) ON messages.target = target_id";
+
+#[async_trait]
+impl MessageStore for SqliteStore {
+    async fn get_or_create_user(&self, name: &str) -> Result<User, StoreError> {
+        let mut conn = self.pool.acquire().await?;
+        match get_user(&mut conn, name).await {
+            Ok(user) => Ok(user),
+            Err(_) => {
+                let result = create_user(&mut conn, name).await?;
+                tracing::info!("Created user {} with result {:?}", name, result);
+                Ok(get_user(&mut conn, name).await?)
+            }
+        }
+    }
+
+    async fn update_profile(
+        &self,
+        name: &str,
+        timezone: &str,
+        locale: &str,
+    ) -> Result<User, StoreError> {
+        let mut conn = self.pool.acquire().await?;
+        if get_user(&mut conn, name).await.is_err() {
+            create_user(&mut conn, name).await?;
+        }
+
+        query("UPDATE users SET timezone = ?, locale = ? WHERE name = ?")
+            .bind(timezone)
+            .bind(locale)
+            .bind(name)
+            .execute(&mut conn)
+            .await?;
+
+        Ok(get_user(&mut conn, name).await?)
+    }
+
+    async fn insert_message(&self, message: ResolvedMessage) -> Result<Message, StoreError> {
+        let mut conn = self.pool.acquire().await?;
+        insert_one(&mut conn, message).await
+    }
+
+    async fn insert_messages(
+        &self,
+        messages: Vec<ResolvedMessage>,
+    ) -> Result<Vec<Message>, StoreError> {
+        let mut tx = self.pool.begin().await?;
+        let mut inserted = Vec::with_capacity(messages.len());
+
+        for (index, message) in messages.into_iter().enumerate() {
+            match insert_one(&mut tx, message).await {
+                Ok(stored) => inserted.push(stored),
+                Err(source) => {
+                    if let Err(rollback_err) = tx.rollback().await {
+                        tracing::error!("Failed to roll back batch insert: {}", rollback_err);
+                    }
+                    return Err(StoreError::BatchFailed {
+                        index,
+                        source: Box::new(source),
+                    });
+                }
+            }
+        }
+
+        tx.commit().await?;
+        Ok(inserted)
+    }
+
+    async fn inbox(
+        &self,
+        target_id: i32,
+        limit: Option<i64>,
+        before: Option<i32>,
+    ) -> Result<Page, StoreError> {
+        let mut conn = self.pool.acquire().await?;
+
+        let messages = match limit {
+            Some(limit) => {
+                let sql = format!(
+                    "{MESSAGE_SELECT} WHERE target_id = ?{cursor} ORDER BY messages.timestamp DESC, messages.id DESC LIMIT ?",
+                    cursor = if before.is_some() { " AND messages.id < ?" } else { "" }
+                );
+                let mut query = sqlx::query_as::<_, Message>(&sql).bind(target_id);
+                if let Some(before) = before {
+                    query = query.bind(before);
+                }
+                let mut page = query.bind(limit).fetch_all(&mut conn).await?;
+                page.reverse();
+                page
+            }
+            None => {
+                sqlx::query_as::<_, Message>(&format!(
+                    "{MESSAGE_SELECT} WHERE target_id = ? ORDER BY timestamp"
+                ))
+                .bind(target_id)
+                .fetch_all(&mut conn)
+                .await?
+            }
+        };
+
+        let next_cursor = next_cursor(&messages, limit);
+        Ok(Page {
+            messages,
+            next_cursor,
+        })
+    }
+
+    async fn conversation(
+        &self,
+        me_id: i32,
+        other_id: i32,
+        limit: Option<i64>,
+        before: Option<i32>,
+    ) -> Result<Page, StoreError> {
+        let mut conn = self.pool.acquire().await?;
+
+        let messages = match limit {
+            Some(limit) => {
+                let sql = format!(
+                    "{MESSAGE_SELECT} WHERE ((author_id = ? AND target_id = ?) OR (author_id = ? AND target_id = ?)){cursor} ORDER BY messages.timestamp DESC, messages.id DESC LIMIT ?",
+                    cursor = if before.is_some() { " AND messages.id < ?" } else { "" }
+                );
+                let mut query = sqlx::query_as::<_, Message>(&sql)
+                    .bind(me_id)
+                    .bind(other_id)
+                    .bind(other_id)
+                    .bind(me_id);
+                if let Some(before) = before {
+                    query = query.bind(before);
+                }
+                let mut page = query.bind(limit).fetch_all(&mut conn).await?;
+                page.reverse();
+                page
+            }
+            None => {
+                sqlx::query_as::<_, Message>(&format!(
+                    "{MESSAGE_SELECT} WHERE (author_id = ? AND target_id = ?) OR (author_id = ? AND target_id = ?) ORDER BY timestamp"
+                ))
+                .bind(me_id)
+                .bind(other_id)
+                .bind(other_id)
+                .bind(me_id)
+                .fetch_all(&mut conn)
+                .await?
+            }
+        };
+
+        let next_cursor = next_cursor(&messages, limit);
+        Ok(Page {
+            messages,
+            next_cursor,
+        })
+    }
+}
+
+/// `Some(oldest message id)` when the page was filled to `limit` (meaning
+/// there may be more history), `None` otherwise.
+fn next_cursor(messages: &[Message], limit: Option<i64>) -> Option<i32> {
+    let limit = limit?;
+    if messages.len() as i64 == limit {
+        messages.first().map(|m| m.id)
+    } else {
+        None
+    }
+}
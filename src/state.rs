@@ -0,0 +1,26 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use tokio::sync::{mpsc, RwLock};
+
+use crate::models::OutgoingMessage;
+use crate::store::MessageStore;
+
+/// Shared application state handed to every handler via `Extension`.
+#[derive(Clone)]
+pub struct AppState {
+    pub store: Arc<dyn MessageStore>,
+    /// Live websocket senders keyed by user name, used to push messages to
+    /// connected clients the moment they're stored. Entries are removed when
+    /// a client disconnects.
+    pub connections: Arc<RwLock<HashMap<String, mpsc::Sender<OutgoingMessage>>>>,
+}
+
+impl AppState {
+    pub fn new(store: Arc<dyn MessageStore>) -> Self {
+        AppState {
+            store,
+            connections: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+}
@@ -0,0 +1,69 @@
+use async_trait::async_trait;
+
+use crate::error::StoreError;
+use crate::models::{Message, ResolvedMessage, User};
+
+/// A cursor-paginated slice of messages, oldest first.
+///
+/// `next_cursor` is the id to pass as `before` to fetch the next (older)
+/// page, or `None` when the page holds fewer than `limit` messages or no
+/// `limit` was given. A full page always carries a cursor, even if nothing
+/// older remains.
+#[derive(Debug)]
+pub struct Page {
+    pub messages: Vec<Message>,
+    pub next_cursor: Option<i32>,
+}
+
+/// Storage abstraction for users and messages.
+///
+/// Handlers depend on this trait rather than a concrete `SqlitePool` so an
+/// alternate backend (e.g. Postgres) can be dropped in without touching the
+/// axum routing. See [`crate::sqlite_store::SqliteStore`] for the default
+/// implementation.
+#[async_trait]
+pub trait MessageStore: Send + Sync {
+    async fn get_or_create_user(&self, name: &str) -> Result<User, StoreError>;
+
+    /// Creates `name` if necessary, then updates their timezone/locale.
+    async fn update_profile(
+        &self,
+        name: &str,
+        timezone: &str,
+        locale: &str,
+    ) -> Result<User, StoreError>;
+
+    /// Inserts a message whose author/target have already been resolved to
+    /// `User`s (see [`ResolvedMessage`]), so the insert itself needs no user
+    /// lookups of its own.
+    async fn insert_message(&self, message: ResolvedMessage) -> Result<Message, StoreError>;
+
+    /// Inserts every message inside a single transaction: either all commit,
+    /// or none do. When a message's insert fails, the returned
+    /// `StoreError::BatchFailed` names its index; a failure to begin or
+    /// commit the transaction is returned as a plain `StoreError::Database`.
+    async fn insert_messages(
+        &self,
+        messages: Vec<ResolvedMessage>,
+    ) -> Result<Vec<Message>, StoreError>;
+
+    /// Messages addressed to `target_id`, oldest first. When `limit` is
+    /// given, returns at most that many of the most recent matching
+    /// messages older than `before` (if supplied).
+    async fn inbox(
+        &self,
+        target_id: i32,
+        limit: Option<i64>,
+        before: Option<i32>,
+    ) -> Result<Page, StoreError>;
+
+    /// The conversation between `me_id` and `other_id`, oldest first, with
+    /// the same `limit`/`before` pagination as [`MessageStore::inbox`].
+    async fn conversation(
+        &self,
+        me_id: i32,
+        other_id: i32,
+        limit: Option<i64>,
+        before: Option<i32>,
+    ) -> Result<Page, StoreError>;
+}
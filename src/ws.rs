@@ -0,0 +1,65 @@
+use axum::extract::ws::{Message as WsMessage, WebSocket, WebSocketUpgrade};
+use axum::extract::Query;
+use axum::response::IntoResponse;
+use axum::Extension;
+use serde::Deserialize;
+use tokio::sync::mpsc;
+
+use crate::state::AppState;
+
+#[derive(Deserialize)]
+pub struct WsAuth {
+    pub name: String,
+}
+
+/// Upgrades to a websocket and streams every `OutgoingMessage` addressed to
+/// `name` as it's stored, instead of making the client poll `/inbox`.
+pub async fn ws_handler(
+    ws: WebSocketUpgrade,
+    Extension(state): Extension<AppState>,
+    Query(auth): Query<WsAuth>,
+) -> impl IntoResponse {
+    ws.on_upgrade(move |socket| handle_socket(socket, state, auth.name))
+}
+
+async fn handle_socket(mut socket: WebSocket, state: AppState, name: String) {
+    let (tx, mut rx) = mpsc::channel(16);
+    state
+        .connections
+        .write()
+        .await
+        .insert(name.clone(), tx.clone());
+    tracing::info!("{} connected over websocket", name);
+
+    loop {
+        tokio::select! {
+            outgoing = rx.recv() => {
+                let Some(message) = outgoing else { break };
+                let payload = match serde_json::to_string(&message) {
+                    Ok(payload) => payload,
+                    Err(e) => {
+                        tracing::error!("Failed to serialize outgoing message: {}", e);
+                        continue;
+                    }
+                };
+                if socket.send(WsMessage::Text(payload)).await.is_err() {
+                    break;
+                }
+            }
+            incoming = socket.recv() => {
+                if incoming.is_none() {
+                    break;
+                }
+            }
+        }
+    }
+
+    // Only remove our own sender: if another socket for the same name
+    // connected in the meantime, it already replaced ours in the map.
+    let mut connections = state.connections.write().await;
+    if connections.get(&name).is_some_and(|current| current.same_channel(&tx)) {
+        connections.remove(&name);
+    }
+    drop(connections);
+    tracing::info!("{} disconnected from websocket", name);
+}